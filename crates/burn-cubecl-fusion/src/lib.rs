@@ -11,3 +11,4 @@ pub(crate) mod shared;
 pub(crate) mod tune;
 
 pub use base::*;
+pub use shared::ir::FusePrecision;
@@ -1,5 +1,6 @@
 pub mod builder;
 pub mod optimization;
+pub mod settings;
 
 pub(crate) mod args;
 pub(crate) mod spec;
@@ -1,5 +1,7 @@
 use std::any::TypeId;
-use std::sync::Arc;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
 
 use crate::FallbackOperation;
 use crate::elemwise::optimization::ElemwiseRunner;
@@ -11,10 +13,13 @@ use crate::shared::trace::TraceError;
 use crate::shared::trace::TuneOutput;
 use crate::shared::trace::Vectorization;
 use crate::shared::trace::VectorizationAxis;
+use crate::shared::trace::VectorizationHandle;
+use crate::shared::trace::vectorization::{Vect, vectorization_default};
 use crate::{CubeFusionHandle, matmul::args::MatmulArg};
 
 use burn_fusion::stream::Context;
-use burn_ir::BinaryOpIr;
+use burn_ir::{BinaryOpIr, TensorId, TensorIr, TensorStatus};
+use burn_tensor::DType;
 use cubecl::features::TypeUsage;
 use cubecl::matmul::components::AccG;
 use cubecl::matmul::components::AccS;
@@ -39,7 +44,7 @@ use cubecl::matmul::{
     components::{LhsS, MatmulLineSizes, MatmulPrecision},
     kernels::layered::Algorithm,
 };
-use cubecl::std::tensor::{MatrixBatchLayout, matrix_batch_layout};
+use cubecl::std::tensor::{MatrixBatchLayout, into_contiguous, matrix_batch_layout};
 use cubecl::{client::ComputeClient, prelude::*};
 use half::{bf16, f16};
 use serde::{Deserialize, Serialize};
@@ -50,17 +55,32 @@ use crate::shared::{
 };
 
 use super::args::FusedMatmulInputLaunch;
+use super::settings::{FusionCounters, MatmulFusionOptions, MatmulFusionSettings, MatmulProfile};
 use super::spec::FusedMatmulSpec;
 use super::tune::fused_matmul_autotune;
 
 /// Fuse matmul operation followed by elemwise operations into a single kernel.
+///
+/// # Epilogue
+///
+/// The element-wise operations following the matmul are applied on the accumulator in registers
+/// (fuse-on-write) before anything is stored. Each tensor produced by those operations is
+/// written with its own precision, so a matmul followed by a cast to a lower precision writes
+/// both the full-precision result and the casted copy from the same kernel, as long as both
+/// tensors are still used afterward. No additional read of the matmul output is performed.
+///
+/// Accumulating into an existing tensor (`c + a.matmul(b)`) fuses the addition the same way:
+/// the tile of `c` is read in the epilogue, and when `c` isn't used afterward its buffer is reused
+/// inplace for the output, so no separate add kernel nor extra allocation is required.
 pub struct MatmulOptimization<R: Runtime> {
     pub(crate) info: Arc<MatmulOptimizationInfo<R>>,
+    settings: Arc<MatmulFusionSettings>,
 }
 
 pub struct MatmulOptimizationTuneArg<R: Runtime> {
     pub(crate) info: Arc<MatmulOptimizationInfo<R>>,
     pub(crate) fallback: Box<dyn FallbackOperation<R>>,
+    pub(crate) settings: Arc<MatmulFusionSettings>,
 }
 
 pub(crate) struct MatmulOptimizationInfo<R: Runtime> {
@@ -70,6 +90,119 @@ pub(crate) struct MatmulOptimizationInfo<R: Runtime> {
     pub(crate) device: R::Device,
     pub(crate) len: usize,
     pub(crate) variants: MatmulVariants,
+    repacked: RepackCache<R>,
+}
+
+/// Maximum number of contiguous copies, and of sources seen once, kept by a
+/// [repack cache](RepackCache).
+const REPACK_CACHE_CAPACITY: usize = 16;
+
+/// Contiguous copies of [repacked](FusedMatmul::with_repack) inputs, keyed by the global id of
+/// the source tensor.
+///
+/// Tensors are immutable until their last use, so a copy stays valid as long as the source id is
+/// alive. This turns the repack of a persistent weight into a one-time cost.
+///
+/// Only read-only sources already repacked by a previous execution are cached, so activations
+/// used once don't keep a copy alive. The least recently used copy is evicted when the cache is
+/// full.
+pub(crate) struct RepackCache<R: Runtime> {
+    state: Mutex<RepackState<R>>,
+}
+
+struct RepackState<R: Runtime> {
+    /// Incremented at the start of each execution of the optimization.
+    execution: u64,
+    /// Sources repacked without being cached, with the execution that repacked them.
+    seen: VecDeque<(TensorId, u64)>,
+    /// Cached copies, from the least to the most recently used.
+    copies: VecDeque<(TensorId, CubeFusionHandle<R>)>,
+    /// Contiguous copies launched since the start of the current execution.
+    launched: u64,
+}
+
+impl<R: Runtime> Default for RepackCache<R> {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(RepackState {
+                execution: 0,
+                seen: VecDeque::new(),
+                copies: VecDeque::new(),
+                launched: 0,
+            }),
+        }
+    }
+}
+
+impl<R: Runtime> RepackCache<R> {
+    /// Start a new execution, so sources repacked from now on are considered reused.
+    ///
+    /// Entries whose source was dropped since the last execution are removed, since their id
+    /// will never be used again.
+    pub(crate) fn begin_execution(&self, context: &Context<'_, CubeFusionHandle<R>>) {
+        let mut state = self.state.lock().unwrap();
+        let alive = |id: &TensorId| context.handles.get_handle_ref(id).is_some();
+
+        state.execution += 1;
+        state.launched = 0;
+        state.seen.retain(|(source, _)| alive(source));
+        state.copies.retain(|(source, _)| alive(source));
+    }
+
+    /// The number of contiguous copies launched by the current execution, including the ones
+    /// launched while autotuning.
+    pub(crate) fn num_launched(&self) -> u64 {
+        self.state.lock().unwrap().launched
+    }
+
+    /// The number of copies currently cached.
+    pub(crate) fn num_cached(&self) -> usize {
+        self.state.lock().unwrap().copies.len()
+    }
+}
+
+impl<R: Runtime> RepackState<R> {
+    /// The cached copy of the source, marked as the most recently used.
+    fn get(&mut self, id: &TensorId) -> Option<CubeFusionHandle<R>> {
+        let index = self.copies.iter().position(|(source, _)| source == id)?;
+        let entry = self.copies.remove(index).unwrap();
+        let copy = entry.1.clone();
+        self.copies.push_back(entry);
+
+        Some(copy)
+    }
+
+    /// Remove every entry of the source, returning its cached copy, if any.
+    fn take(&mut self, id: &TensorId) -> Option<CubeFusionHandle<R>> {
+        self.seen.retain(|(source, _)| source != id);
+        let index = self.copies.iter().position(|(source, _)| source == id)?;
+
+        self.copies.remove(index).map(|(_, copy)| copy)
+    }
+
+    /// Record a new copy of the source, caching it when the source was already repacked by a
+    /// previous execution.
+    fn record(&mut self, id: TensorId, copy: CubeFusionHandle<R>) {
+        let execution = self.execution;
+
+        match self.seen.iter().position(|(source, _)| *source == id) {
+            Some(index) if self.seen[index].1 < execution => {
+                self.seen.remove(index);
+                if self.copies.len() >= REPACK_CACHE_CAPACITY {
+                    self.copies.pop_front();
+                }
+                self.copies.push_back((id, copy));
+            }
+            // Repacked again while autotuning the same execution.
+            Some(_) => {}
+            None => {
+                if self.seen.len() >= REPACK_CACHE_CAPACITY {
+                    self.seen.pop_front();
+                }
+                self.seen.push_back((id, execution));
+            }
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -113,6 +246,22 @@ impl MatmulVariants {
             ordered: selector(FusedMatmulSelector::OrderedDoubleBuffering),
         }
     }
+
+    /// The variants with the given [options](MatmulFusionOptions) applied.
+    pub(crate) fn with_options(&self, options: MatmulFusionOptions) -> Self {
+        let apply = |matmul: &FusedMatmul| matmul.clone().with_options(options);
+        Self {
+            simple_unit: apply(&self.simple_unit),
+            simple_vec_mat: apply(&self.simple_vec_mat),
+            double_vec_mat: apply(&self.double_vec_mat),
+            double_unit: apply(&self.double_unit),
+            simple: apply(&self.simple),
+            simple_multi_rows: apply(&self.simple_multi_rows),
+            double_buffering: apply(&self.double_buffering),
+            specialized: apply(&self.specialized),
+            ordered: apply(&self.ordered),
+        }
+    }
 }
 
 impl<R: Runtime> MatmulOptimizationInfo<R> {
@@ -125,6 +274,34 @@ impl<R: Runtime> MatmulOptimizationInfo<R> {
     pub fn num_ops_fused(&self) -> usize {
         self.len
     }
+
+    /// The computation precision of every element-wise operation fused with the matmul, in
+    /// execution order.
+    pub fn fused_precisions(&self) -> Vec<FusePrecision> {
+        self.trace
+            .blocks
+            .iter()
+            .flat_map(|block| block.ops.iter().map(|op| op.cmp_precision()))
+            .collect()
+    }
+
+    /// Whether the fallback executes a fused element-wise trace after the unfused matmul.
+    pub fn has_fallback(&self) -> bool {
+        self.trace_fallback
+            .blocks
+            .iter()
+            .any(|block| !block.ops.is_empty())
+    }
+
+    /// A [summary](FusedMatmulSummary) of the fused trace.
+    pub fn summary(&self) -> FusedMatmulSummary {
+        FusedMatmulSummary {
+            num_ops: self.num_ops_fused(),
+            num_output_buffers: self.num_output_buffers(),
+            precisions: self.fused_precisions(),
+            has_fallback: self.has_fallback(),
+        }
+    }
 }
 
 impl<R: Runtime> MatmulOptimizationTuneArg<R> {
@@ -132,11 +309,19 @@ impl<R: Runtime> MatmulOptimizationTuneArg<R> {
         &self,
         context: &mut Context<'_, CubeFusionHandle<R>>,
     ) -> Result<TuneOutput<R>, TraceError<FusedMatmulError>> {
+        let matmul = S::select(&self.info.variants);
+
+        if matmul.repack {
+            let counters = self.settings.counters();
+            repack_input(&self.info.repacked, counters, context, &matmul.op.lhs);
+            repack_input(&self.info.repacked, counters, context, &matmul.op.rhs);
+        }
+
         self.info.trace.run::<R, BT, FusedMatmul>(
             &self.info.client,
             &self.info.device,
             context,
-            S::select(&self.info.variants),
+            matmul,
         )
     }
 
@@ -182,6 +367,76 @@ impl<R: Runtime> MatmulOptimizationTuneArg<R> {
 
         output.merge(output_write)
     }
+
+    /// Count the execution and report the executed kernel to the profiling hook, if any.
+    ///
+    /// Must only be called with the original context, never with a fork used for benchmarking.
+    pub(crate) fn profile(
+        &self,
+        context: &Context<'_, CubeFusionHandle<R>>,
+        selector: Option<FusedMatmulSelector>,
+    ) {
+        let counters = self.settings.counters();
+        let counter = match selector {
+            Some(_) => &counters.fused,
+            None => &counters.fallback,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        let Some(hook) = self.settings.profiling_hook() else {
+            return;
+        };
+        let op = &self.info.variants.simple.op;
+        let shape = |id| context.tensors.get(id).unwrap().shape.dims.clone();
+
+        hook(&MatmulProfile {
+            selector,
+            lhs_shape: shape(&op.lhs.id),
+            rhs_shape: shape(&op.rhs.id),
+            repack_count: self.info.repacked.num_launched(),
+            cached_repacks: self.info.repacked.num_cached(),
+            summary: self.info.summary(),
+        });
+    }
+
+    /// Check the layout of the inputs before selecting a kernel, so that inputs no fused kernel
+    /// can handle go straight to the fallback instead of being autotuned.
+    pub(crate) fn check_inputs(
+        &self,
+        context: &Context<'_, CubeFusionHandle<R>>,
+    ) -> Result<(), FusedMatmulError> {
+        let matmul = &self.info.variants.simple;
+
+        // Highly permuted inputs are copied to a contiguous layout before the launch.
+        if matmul.repack {
+            return Ok(());
+        }
+
+        let lhs = context.tensors.get(&matmul.op.lhs.id).unwrap();
+        let rhs = context.tensors.get(&matmul.op.rhs.id).unwrap();
+        let lhs_handle = context.handles.get_handle_ref(&lhs.id).unwrap();
+        let rhs_handle = context.handles.get_handle_ref(&rhs.id).unwrap();
+
+        check_input_layouts(
+            &lhs.shape.dims,
+            &rhs.shape.dims,
+            &lhs_handle.strides,
+            &rhs_handle.strides,
+        )
+        .map(|_| ())
+    }
+
+    /// Count the error that made the fused kernels fall back.
+    ///
+    /// Must only be called with the original context, never with a fork used for benchmarking.
+    pub(crate) fn record_error(&self, error: &TraceError<FusedMatmulError>) {
+        if let TraceError::RunnerError(FusedMatmulError::InvalidInput) = error {
+            self.settings
+                .counters()
+                .invalid_input
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
 }
 
 impl<R: Runtime> MatmulOptimization<R> {
@@ -193,6 +448,8 @@ impl<R: Runtime> MatmulOptimization<R> {
         len: usize,
         matmul: FusedMatmul,
     ) -> Self {
+        let settings = MatmulFusionSettings::get_with_client::<R>(&client, &device);
+        let matmul = matmul.with_options(settings.options());
         let variants = MatmulVariants::from_default(&matmul, &trace);
 
         let info = MatmulOptimizationInfo {
@@ -202,31 +459,107 @@ impl<R: Runtime> MatmulOptimization<R> {
             device,
             len,
             variants,
+            repacked: Default::default(),
         };
 
         Self {
             info: Arc::new(info),
+            settings,
         }
     }
+
     /// Execute the optimization.
     pub fn execute<BT: CubeElement>(
         &mut self,
         context: &mut Context<'_, CubeFusionHandle<R>>,
         fallback: impl FnOnce(usize) -> Box<dyn FallbackOperation<R>>,
     ) {
-        // The index of the fallback matmul is always 0.
-        let fallback = fallback(0);
-        let arg = MatmulOptimizationTuneArg {
-            info: self.info.clone(),
-            fallback,
-        };
+        self.sync_options();
+        self.info.repacked.begin_execution(context);
 
-        #[cfg(feature = "autotune")]
-        fused_matmul_autotune::<R, BT>(arg, context);
+        if self.settings.fusion_disabled() {
+            self.execute_unfused::<BT>(context, fallback);
+            return;
+        }
+
+        let arg = self.tune_arg(fallback);
 
-        #[cfg(not(feature = "autotune"))]
-        if arg.execute_fused::<BT, Simple>(context).is_err() {
+        if let Err(err) = arg.check_inputs(context) {
+            arg.record_error(&TraceError::RunnerError(err));
             arg.execute_fallback::<BT>(context);
+            arg.profile(context, None);
+            return;
+        }
+
+        // Deterministic matmuls always run the same selector, since autotune could pick a
+        // kernel with a different accumulation order from one process to the next.
+        #[cfg(feature = "autotune")]
+        if !self.info.variants.simple.deterministic {
+            fused_matmul_autotune::<R, BT>(arg, context);
+            return;
+        }
+
+        // The unit matmul doesn't require tensor cores, so it's tried before falling back when the
+        // accelerated kernel can't be launched on the current hardware.
+        let selector = if arg.execute_fused::<BT, Simple>(context).is_ok() {
+            Some(FusedMatmulSelector::Simple)
+        } else {
+            match arg.execute_fused::<BT, SimpleUnit>(context) {
+                Ok(_) => Some(FusedMatmulSelector::SimpleUnit),
+                Err(err) => {
+                    arg.record_error(&err);
+                    arg.execute_fallback::<BT>(context);
+                    None
+                }
+            }
+        };
+        arg.profile(context, selector);
+    }
+
+    /// Execute the optimization without fusing the matmul, running the fallback matmul followed
+    /// by the element-wise trace.
+    pub fn execute_unfused<BT: CubeElement>(
+        &mut self,
+        context: &mut Context<'_, CubeFusionHandle<R>>,
+        fallback: impl FnOnce(usize) -> Box<dyn FallbackOperation<R>>,
+    ) {
+        let arg = self.tune_arg(fallback);
+
+        arg.execute_fallback::<BT>(context);
+        arg.profile(context, None);
+    }
+
+    /// Apply the [options](MatmulFusionOptions) of the device when they changed since the last
+    /// execution.
+    fn sync_options(&mut self) {
+        let options = self.settings.options();
+
+        if self.info.variants.simple.options() == options {
+            return;
+        }
+
+        // The cached copies may come from a repack that is no longer enabled, so they're dropped.
+        let info = MatmulOptimizationInfo {
+            trace: self.info.trace.clone(),
+            trace_fallback: self.info.trace_fallback.clone(),
+            client: self.info.client.clone(),
+            device: self.info.device.clone(),
+            len: self.info.len,
+            variants: self.info.variants.with_options(options),
+            repacked: Default::default(),
+        };
+        self.info = Arc::new(info);
+    }
+
+    fn tune_arg(
+        &self,
+        fallback: impl FnOnce(usize) -> Box<dyn FallbackOperation<R>>,
+    ) -> MatmulOptimizationTuneArg<R> {
+        MatmulOptimizationTuneArg {
+            info: self.info.clone(),
+            // The index of the fallback matmul is always 0.
+            fallback: fallback(0),
+            settings: self.settings.clone(),
         }
     }
 
@@ -235,8 +568,30 @@ impl<R: Runtime> MatmulOptimization<R> {
         self.info.num_ops_fused()
     }
 
+    /// The computation precision of every element-wise operation fused with the matmul, in
+    /// execution order.
+    pub fn fused_precisions(&self) -> Vec<FusePrecision> {
+        self.info.fused_precisions()
+    }
+
+    /// Whether the fallback executes a fused element-wise trace after the unfused matmul.
+    pub fn has_fallback(&self) -> bool {
+        self.info.has_fallback()
+    }
+
+    /// A [summary](FusedMatmulSummary) of the fused trace.
+    pub fn summary(&self) -> FusedMatmulSummary {
+        self.info.summary()
+    }
+
+    /// A short human-readable summary of the fused trace.
+    pub fn describe(&self) -> String {
+        self.summary().to_string()
+    }
+
     /// Create an optimization from its [state](MatmulOptimizationState).
     pub fn from_state(device: &R::Device, state: MatmulOptimizationState) -> Self {
+        let settings = MatmulFusionSettings::get::<R>(device);
         let info = MatmulOptimizationInfo {
             trace: state.trace,
             trace_fallback: state.trace_fallback,
@@ -244,10 +599,12 @@ impl<R: Runtime> MatmulOptimization<R> {
             client: R::client(device),
             device: device.clone(),
             variants: state.variants.clone(),
+            repacked: Default::default(),
         };
 
         Self {
             info: Arc::new(info),
+            settings,
         }
     }
 
@@ -262,6 +619,35 @@ impl<R: Runtime> MatmulOptimization<R> {
     }
 }
 
+/// Summary of the trace fused by a [matmul optimization](MatmulOptimization).
+///
+/// Displayed as:
+///
+/// ```text
+/// FusedMatmul { ops: 2, output_buffers: 1, precisions: [F32], fallback_trace: true }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FusedMatmulSummary {
+    /// See [MatmulOptimization::num_ops_fused].
+    pub num_ops: usize,
+    /// The number of output buffers added by fusion.
+    pub num_output_buffers: usize,
+    /// See [MatmulOptimization::fused_precisions].
+    pub precisions: Vec<FusePrecision>,
+    /// See [MatmulOptimization::has_fallback].
+    pub has_fallback: bool,
+}
+
+impl core::fmt::Display for FusedMatmulSummary {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "FusedMatmul {{ ops: {}, output_buffers: {}, precisions: {:?}, fallback_trace: {} }}",
+            self.num_ops, self.num_output_buffers, self.precisions, self.has_fallback,
+        )
+    }
+}
+
 #[derive(Default, Clone, Serialize, Deserialize, Debug)]
 pub enum FusedMatmulSelector {
     #[default]
@@ -283,12 +669,82 @@ pub struct FusedMatmul {
     out: Arg,
     pub(crate) op: BinaryOpIr,
     pub(crate) selector: FusedMatmulSelector,
+    #[new(default)]
+    pub(crate) deterministic: bool,
+    #[new(default)]
+    pub(crate) repack: bool,
+    #[new(default)]
+    pub(crate) accumulation: AccumulationPrecision,
+}
+
+/// Precision used to accumulate the products of a [fused matmul](FusedMatmul).
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+pub enum AccumulationPrecision {
+    /// Use the accumulation precision of the kernel, which promotes half-precision inputs.
+    #[default]
+    Promoted,
+    /// Accumulate in the output precision, saving registers at the cost of accuracy.
+    Output,
+}
+
+impl FusedMatmul {
+    /// Force a fixed accumulation order so that repeated runs produce bit-identical results.
+    ///
+    /// # Notes
+    ///
+    /// This is an experimental option that bypasses autotune and always launches the
+    /// [simple](FusedMatmulSelector::Simple) algorithm, or its
+    /// [unit](FusedMatmulSelector::SimpleUnit) counterpart when tensor cores aren't available, so
+    /// double-buffered kernels that reorder the accumulation are never used. Expect a slowdown on
+    /// large problems, where those kernels are normally selected.
+    pub fn with_deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Copy highly permuted inputs into contiguous buffers before launching the fused kernel,
+    /// instead of falling back to the unfused matmul.
+    ///
+    /// # Notes
+    ///
+    /// The copy is launched as part of the fused execution, so its cost is included in the
+    /// autotune measurements. The contiguous buffer replaces the original handle for the
+    /// following operations.
+    pub fn with_repack(mut self, repack: bool) -> Self {
+        self.repack = repack;
+        self
+    }
+
+    /// Set the [precision](AccumulationPrecision) used to accumulate the products.
+    pub fn with_accumulation(mut self, accumulation: AccumulationPrecision) -> Self {
+        self.accumulation = accumulation;
+        self
+    }
+
+    /// Apply every option set on the device [settings](MatmulFusionSettings).
+    pub fn with_options(self, options: MatmulFusionOptions) -> Self {
+        self.with_deterministic(options.deterministic)
+            .with_repack(options.repack)
+            .with_accumulation(options.accumulation)
+    }
+
+    pub(crate) fn options(&self) -> MatmulFusionOptions {
+        MatmulFusionOptions {
+            deterministic: self.deterministic,
+            repack: self.repack,
+            accumulation: self.accumulation,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum FusedMatmulError {
     LaunchError(MatmulSetupError),
     InvalidInput,
+    /// The output precision can't be used by the matmul kernels on the current runtime.
+    UnsupportedPrecision(FusePrecision),
+    /// The reference layout of the fused block isn't a global tensor.
+    InvalidRefLayout,
 }
 
 impl From<MatmulSetupError> for FusedMatmulError {
@@ -297,6 +753,27 @@ impl From<MatmulSetupError> for FusedMatmulError {
     }
 }
 
+/// The output precisions the fused matmul kernels can be launched with on the given client.
+///
+/// Fusing a matmul with any other precision always falls back to the unfused matmul.
+pub fn supported_matmul_precisions<R: Runtime>(
+    client: &ComputeClient<R::Server>,
+) -> Vec<FusePrecision> {
+    let mut precisions = vec![FusePrecision::F32, FusePrecision::Flex32];
+
+    if f16::supported_uses(client).contains(TypeUsage::Arithmetic) {
+        precisions.push(FusePrecision::F16);
+    }
+    if bf16::supported_uses(client).contains(TypeUsage::Arithmetic) {
+        precisions.push(FusePrecision::BF16);
+    }
+    if f64::supported_uses(client).contains(TypeUsage::Arithmetic) {
+        precisions.push(FusePrecision::F64);
+    }
+
+    precisions
+}
+
 impl<R: Runtime> Vectorization<R> for FusedMatmul {
     fn axis(&self, plan: &LaunchPlan<'_, R>) -> VectorizationAxis {
         let lhs_id = self.op.lhs.id;
@@ -348,6 +825,73 @@ impl<R: Runtime> Vectorization<R> for FusedMatmul {
 
         axis
     }
+
+    #[allow(clippy::too_many_arguments)]
+    fn vectorization<'a>(
+        &self,
+        context: &Context<'_, CubeFusionHandle<R>>,
+        vectorizations: &mut BTreeMap<TensorId, Vect>,
+        inputs: impl Iterator<Item = VectorizationHandle<'a, R>>,
+        outputs: impl Iterator<Item = &'a TensorIr>,
+        reshaped: impl Iterator<Item = (&'a TensorIr, &'a TensorIr, bool)>,
+        swapped: impl Iterator<Item = (&'a TensorIr, &'a TensorIr, bool, &'a (u32, u32))>,
+        line_sizes: &[u8],
+        max: u8,
+        axis: VectorizationAxis,
+        reference: Option<TensorId>,
+    ) {
+        vectorization_default(
+            vectorizations,
+            inputs,
+            outputs,
+            reshaped,
+            swapped,
+            line_sizes,
+            &Default::default(),
+            max,
+            &axis,
+        );
+
+        // The matmul writes lines of the size of the reference layout, which can't be smaller
+        // than the ones it reads. A virtual reference is always written without vectorization.
+        let ref_line_size = reference
+            .and_then(|id| vectorizations.get(&id))
+            .map(|vect| vect.line_size())
+            .unwrap_or(1);
+
+        if ref_line_size > 1 {
+            return;
+        }
+
+        let lhs = context.tensors.get(&self.op.lhs.id).unwrap();
+        let rhs = context.tensors.get(&self.op.rhs.id).unwrap();
+        let line_size = |tensor: &TensorIr| {
+            vectorizations
+                .get(&tensor.id)
+                .map(|vect| vect.line_size())
+                .unwrap_or(1)
+        };
+        let (lhs_line_size, rhs_line_size) = (line_size(lhs), line_size(rhs));
+
+        // When reading the inputs without vectorization is estimated slower than the fallback,
+        // the line sizes are kept so the launch fails with an invalid input.
+        if (lhs_line_size == 1 && rhs_line_size == 1)
+            || !unvectorized_reads_are_cheaper(
+                &lhs.shape.dims,
+                &rhs.shape.dims,
+                lhs_line_size,
+                rhs_line_size,
+            )
+        {
+            return;
+        }
+
+        for id in [lhs.id, rhs.id] {
+            if let Some(vect) = vectorizations.get_mut(&id) {
+                *vect = vect.limit_to_one();
+            }
+        }
+    }
 }
 
 impl<R: Runtime> TraceRunner<R> for FusedMatmul {
@@ -360,7 +904,18 @@ impl<R: Runtime> TraceRunner<R> for FusedMatmul {
         outputs: GlobalArgsLaunch<'a, R>,
         configs: &'a [FuseBlockConfig],
     ) -> Result<(), FusedMatmulError> {
-        match self.out.precision() {
+        let precision = self.out.precision();
+
+        // The kernels are instantiated with a single global precision for both operands, so
+        // operands of different float types can't be read correctly. Quantized operands are
+        // dequantized to the matmul precision in the load stage.
+        if let (MatmulArg::Normal(lhs), MatmulArg::Normal(rhs)) = (&self.lhs, &self.rhs)
+            && lhs.precision() != rhs.precision()
+        {
+            return Err(FusedMatmulError::UnsupportedPrecision(rhs.precision()));
+        }
+
+        match precision {
             FusePrecision::F32 => self.matmul_fused::<R, f32>(client, inputs, outputs, &configs[0]),
             FusePrecision::Flex32 => {
                 self.matmul_fused::<R, flex32>(client, inputs, outputs, &configs[0])
@@ -369,7 +924,15 @@ impl<R: Runtime> TraceRunner<R> for FusedMatmul {
             FusePrecision::BF16 => {
                 self.matmul_fused::<R, bf16>(client, inputs, outputs, &configs[0])
             }
-            _ => panic!("Unsupported precision"),
+            FusePrecision::F64 => {
+                // Double precision is rarely available, and never on tensor cores, so the
+                // accelerated selectors fail to launch and the unit ones are used instead.
+                if !f64::supported_uses(client).contains(TypeUsage::Arithmetic) {
+                    return Err(FusedMatmulError::UnsupportedPrecision(precision));
+                }
+                self.matmul_fused::<R, f64>(client, inputs, outputs, &configs[0])
+            }
+            _ => Err(FusedMatmulError::UnsupportedPrecision(precision)),
         }
     }
 }
@@ -389,28 +952,24 @@ impl FusedMatmul {
         let lhs_strides = inputs.strides(self.lhs.data());
         let rhs_strides = inputs.strides(self.rhs.data());
 
-        let check_layout = |strides| match matrix_batch_layout(strides) {
-            MatrixBatchLayout::Contiguous => (false, false),
-            MatrixBatchLayout::MildlyPermuted {
-                transposed,
-                batch_swap: _,
-            } => (false, transposed),
-            MatrixBatchLayout::HighlyPermuted => (true, false),
-        };
-
-        let (lhs_make_contiguous, lhs_transposed) = check_layout(&lhs_strides);
-        let (rhs_make_contiguous, rhs_transposed) = check_layout(&rhs_strides);
-
-        if lhs_make_contiguous || rhs_make_contiguous {
-            return Err(FusedMatmulError::InvalidInput);
-        }
+        let (lhs_transposed, rhs_transposed) =
+            check_input_layouts(&lhs_shape, &rhs_shape, &lhs_strides, &rhs_strides)?;
 
         let rank = lhs_shape.len();
-
         let m = lhs_shape[rank - 2] as u32;
         let k = lhs_shape[rank - 1] as u32;
         let n = rhs_shape[rank - 1] as u32;
 
+        // An empty output is already allocated with the right shape, there is nothing to compute.
+        if out_shape.contains(&0) {
+            return Ok(());
+        }
+
+        // The output must be filled with zeros, which the fallback handles.
+        if k == 0 {
+            return Err(FusedMatmulError::InvalidInput);
+        }
+
         let mut line_sizes = MatmulLineSizes {
             lhs: inputs.line_size(self.lhs.data()),
             rhs: inputs.line_size(self.rhs.data()),
@@ -418,7 +977,7 @@ impl FusedMatmul {
                 RefLayout::Concrete(arg) => match arg {
                     Arg::Input(..) => inputs.line_size(arg),
                     Arg::Output(..) => outputs.line_size(arg),
-                    _ => panic!("Invalid ref layout"),
+                    _ => return Err(FusedMatmulError::InvalidRefLayout),
                 },
                 RefLayout::Virtual(_) => 1,
             },
@@ -458,6 +1017,7 @@ impl FusedMatmul {
 
                 match launch_inner_fix_dtype::<R, EG, SimpleAlgorithm<AcceleratedMatmul<Filled>>>(
                     client,
+                    self.accumulation,
                     FusedMatmulInputLaunch::new(
                         inputs,
                         config.clone(),
@@ -484,6 +1044,7 @@ impl FusedMatmul {
                     CyclicDoubleBufferingAlgorithm<AcceleratedMatmul<Filled>>,
                 >(
                     client,
+                    self.accumulation,
                     FusedMatmulInputLaunch::new(
                         inputs,
                         config.clone(),
@@ -513,6 +1074,7 @@ impl FusedMatmul {
                     OrderedDoubleBufferingAlgorithm<AcceleratedMatmul<Filled>>,
                 >(
                     client,
+                    self.accumulation,
                     FusedMatmulInputLaunch::new(
                         inputs,
                         config.clone(),
@@ -537,6 +1099,7 @@ impl FusedMatmul {
             FusedMatmulSelector::SimpleUnit => {
                 match launch_inner_fix_dtype::<R, EG, SimpleUnitAlgorithm>(
                     client,
+                    self.accumulation,
                     FusedMatmulInputLaunch::new(
                         inputs,
                         config.clone(),
@@ -557,6 +1120,7 @@ impl FusedMatmul {
             FusedMatmulSelector::DoubleUnit => {
                 match launch_inner_fix_dtype::<R, EG, DoubleUnitAlgorithm>(
                     client,
+                    self.accumulation,
                     FusedMatmulInputLaunch::new(
                         inputs,
                         config.clone(),
@@ -577,6 +1141,7 @@ impl FusedMatmul {
             FusedMatmulSelector::SimpleVecMat => {
                 match launch_inner_fix_dtype::<R, EG, SimpleVecMatAlgorithm>(
                     client,
+                    self.accumulation,
                     FusedMatmulInputLaunch::new(
                         inputs,
                         config.clone(),
@@ -597,6 +1162,7 @@ impl FusedMatmul {
             FusedMatmulSelector::DoubleVecMat => {
                 match launch_inner_fix_dtype::<R, EG, DoubleVecMatAlgorithm>(
                     client,
+                    self.accumulation,
                     FusedMatmulInputLaunch::new(
                         inputs,
                         config.clone(),
@@ -620,6 +1186,7 @@ impl FusedMatmul {
 
 fn launch_inner_fix_dtype<'a, R: Runtime, MP: MatmulPrecision, A: Algorithm>(
     client: &ComputeClient<R::Server>,
+    accumulation: AccumulationPrecision,
     input: FusedMatmulInputLaunch<'a, R>,
     output: GlobalArgsLaunch<'a, R>,
     problem: MatmulProblem,
@@ -637,6 +1204,20 @@ fn launch_inner_fix_dtype<'a, R: Runtime, MP: MatmulPrecision, A: Algorithm>(
 
     let plane_size = fix_plane_dim(A::select_plane_dim::<R>(client));
 
+    // Only needed when the kernel promotes the accumulator, otherwise the tf32 conversion below
+    // still applies.
+    if let AccumulationPrecision::Output = accumulation
+        && TypeId::of::<AccG<MP>>() != TypeId::of::<AccS<MP>>()
+    {
+        return launch_kernel_virtual::<
+            FusedMatmulSpec<(LhsG<MP>, RhsG<MP>, AccG<MP>, LhsS<MP>, RhsS<MP>, AccG<MP>)>,
+            R,
+            A,
+        >(
+            client, input, output, problem, line_sizes, plane_size, selection,
+        );
+    }
+
     if <A::TileMatmul as TileMatmulFamily>::requires_accelerator()
         && tf32::supported_uses(client).contains(TypeUsage::Conversion)
     {
@@ -676,6 +1257,140 @@ fn launch_inner_fix_dtype<'a, R: Runtime, MP: MatmulPrecision, A: Algorithm>(
     }
 }
 
+/// Estimate whether a fused matmul reading its inputs without vectorization beats the fallback,
+/// by comparing the number of global memory transactions of both.
+///
+/// The fused kernel reads every input value one at a time, while the fallback reads vectorized
+/// inputs but writes the matmul output and reads it back in the element-wise kernel. The final
+/// write is the same for both.
+fn unvectorized_reads_are_cheaper(
+    lhs_shape: &[usize],
+    rhs_shape: &[usize],
+    lhs_line_size: u8,
+    rhs_line_size: u8,
+) -> bool {
+    let rank = lhs_shape.len();
+    let batches: usize = lhs_shape[..rank - 2]
+        .iter()
+        .zip(&rhs_shape[..rank - 2])
+        .map(|(lhs, rhs)| usize::max(*lhs, *rhs))
+        .product();
+    let num_out = batches * lhs_shape[rank - 2] * rhs_shape[rank - 1];
+    let num_lhs: usize = lhs_shape.iter().product();
+    let num_rhs: usize = rhs_shape.iter().product();
+
+    let fused = num_lhs + num_rhs;
+    let fallback =
+        num_lhs / lhs_line_size as usize + num_rhs / rhs_line_size as usize + 2 * num_out;
+
+    fused <= fallback
+}
+
+/// Check that the fused kernels can read the inputs with their current layout, returning whether
+/// each of them is transposed.
+fn check_input_layouts(
+    lhs_shape: &[usize],
+    rhs_shape: &[usize],
+    lhs_strides: &[usize],
+    rhs_strides: &[usize],
+) -> Result<(bool, bool), FusedMatmulError> {
+    let check_layout = |strides| match matrix_batch_layout(strides) {
+        MatrixBatchLayout::Contiguous => Ok(false),
+        // Swapped batch dimensions don't need a copy, since the global layout reads the stride of
+        // every batch dimension from the tensor itself.
+        MatrixBatchLayout::MildlyPermuted {
+            transposed,
+            batch_swap: _,
+        } => Ok(transposed),
+        MatrixBatchLayout::HighlyPermuted => Err(FusedMatmulError::InvalidInput),
+    };
+
+    let lhs_transposed = check_layout(lhs_strides)?;
+    let rhs_transposed = check_layout(rhs_strides)?;
+
+    let rank = lhs_shape.len();
+
+    // Batch dimensions are broadcast by the matmul kernels, so each of them must either match or
+    // have a size of 1 on one side.
+    let broadcastable = lhs_shape[..rank - 2]
+        .iter()
+        .zip(&rhs_shape[..rank - 2])
+        .all(|(lhs, rhs)| lhs == rhs || *lhs == 1 || *rhs == 1);
+
+    if !broadcastable {
+        return Err(FusedMatmulError::InvalidInput);
+    }
+
+    Ok((lhs_transposed, rhs_transposed))
+}
+
+/// Replace the handle of the given input with a contiguous copy when its layout can't be read by
+/// the matmul kernels.
+fn repack_input<R: Runtime>(
+    cache: &RepackCache<R>,
+    counters: &FusionCounters,
+    context: &mut Context<'_, CubeFusionHandle<R>>,
+    tensor: &TensorIr,
+) {
+    let tensor_global = context.tensors.get(&tensor.id).unwrap().clone();
+    let mut state = cache.state.lock().unwrap();
+
+    // The last use of a tensor may write to its buffer, and its id is never used again.
+    let cached = match tensor_global.status {
+        TensorStatus::ReadWrite => state.take(&tensor_global.id),
+        _ => state.get(&tensor_global.id),
+    };
+    if let Some(repacked) = cached {
+        context.handles.register_handle(tensor_global.id, repacked);
+        return;
+    }
+
+    let handle = context
+        .handles
+        .get_handle(&tensor_global.id, &TensorStatus::ReadOnly);
+
+    if handle.qparams.is_some()
+        || !matches!(
+            matrix_batch_layout(&handle.strides),
+            MatrixBatchLayout::HighlyPermuted
+        )
+    {
+        return;
+    }
+
+    let shape = &tensor_global.shape.dims;
+    let (buffer, strides) = match handle.dtype {
+        DType::F64 => contiguous_copy::<R, f64>(&handle, shape),
+        DType::F32 | DType::Flex32 => contiguous_copy::<R, f32>(&handle, shape),
+        DType::F16 => contiguous_copy::<R, f16>(&handle, shape),
+        DType::BF16 => contiguous_copy::<R, bf16>(&handle, shape),
+        _ => return,
+    };
+
+    let repacked = CubeFusionHandle {
+        handle: buffer,
+        strides,
+        ..handle
+    };
+    counters.repacked.fetch_add(1, Ordering::Relaxed);
+    state.launched += 1;
+
+    if !matches!(tensor_global.status, TensorStatus::ReadWrite) {
+        state.record(tensor_global.id, repacked.clone());
+    }
+
+    context.handles.register_handle(tensor_global.id, repacked);
+}
+
+fn contiguous_copy<R: Runtime, E: CubePrimitive>(
+    handle: &CubeFusionHandle<R>,
+    shape: &[usize],
+) -> (cubecl::server::Handle, Vec<usize>) {
+    let output = into_contiguous::<R, E>(&handle.client, &handle.as_handle_ref(shape));
+
+    (output.handle, output.strides)
+}
+
 pub(crate) trait MatmulVariantSelection {
     fn select(variants: &MatmulVariants) -> &FusedMatmul;
 }
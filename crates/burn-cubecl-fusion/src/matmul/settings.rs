@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use cubecl::{CubeTuneId, Runtime, client::ComputeClient};
+
+use super::optimization::{AccumulationPrecision, FusedMatmulSelector, FusedMatmulSummary};
+
+static SETTINGS: Mutex<Option<HashMap<CubeTuneId, Arc<MatmulFusionSettings>>>> = Mutex::new(None);
+
+/// Settings and statistics shared by every fused matmul executed on a device.
+///
+/// The settings are read by each [matmul optimization](super::optimization::MatmulOptimization)
+/// when it is executed, so a change applies from the next execution on.
+#[derive(Default)]
+pub struct MatmulFusionSettings {
+    options: Mutex<MatmulFusionOptions>,
+    profiling_hook: Mutex<Option<MatmulProfilingHook>>,
+    fusion_disabled: AtomicBool,
+    divergence_threshold: Mutex<Option<f64>>,
+    counters: FusionCounters,
+}
+
+/// Options applied to every fused matmul of a device.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatmulFusionOptions {
+    /// See [FusedMatmul::with_deterministic](super::optimization::FusedMatmul::with_deterministic).
+    pub deterministic: bool,
+    /// See [FusedMatmul::with_repack](super::optimization::FusedMatmul::with_repack).
+    pub repack: bool,
+    /// See [FusedMatmul::with_accumulation](super::optimization::FusedMatmul::with_accumulation).
+    pub accumulation: AccumulationPrecision,
+}
+
+/// Callback invoked with the [profile](MatmulProfile) of every executed fused matmul.
+pub type MatmulProfilingHook = Arc<dyn Fn(&MatmulProfile) + Send + Sync>;
+
+/// Information about the kernel that was executed for a fused matmul.
+#[derive(Debug, Clone)]
+pub struct MatmulProfile {
+    /// The selector of the fused kernel, or `None` when the fallback was executed.
+    pub selector: Option<FusedMatmulSelector>,
+    /// The shape of the lhs tensor.
+    pub lhs_shape: Vec<usize>,
+    /// The shape of the rhs tensor.
+    pub rhs_shape: Vec<usize>,
+    /// Contiguous copies launched by this execution to [repack](MatmulFusionOptions::repack)
+    /// highly permuted inputs, including the ones launched while autotuning.
+    pub repack_count: u64,
+    /// The number of repacked copies cached by the optimization after this execution.
+    pub cached_repacks: usize,
+    /// A summary of the trace fused with the matmul.
+    pub summary: FusedMatmulSummary,
+}
+
+/// Number of executions of the fused matmuls of a device per path taken.
+///
+/// Executions done while autotuning candidates aren't counted.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FusionStats {
+    /// Executions that ran a fused matmul kernel.
+    pub fused_count: u64,
+    /// Executions that ran the unfused matmul followed by the element-wise trace.
+    pub fallback_count: u64,
+    /// Fallback executions caused by inputs the fused kernels can't handle, also included in
+    /// `fallback_count`.
+    pub invalid_input_count: u64,
+    /// Contiguous copies launched to [repack](MatmulFusionOptions::repack) highly permuted inputs,
+    /// including the ones launched while autotuning.
+    pub repack_count: u64,
+}
+
+#[derive(Default, Debug)]
+pub(crate) struct FusionCounters {
+    pub(crate) fused: AtomicU64,
+    pub(crate) fallback: AtomicU64,
+    pub(crate) invalid_input: AtomicU64,
+    pub(crate) repacked: AtomicU64,
+}
+
+impl MatmulFusionSettings {
+    /// The settings of the given device.
+    pub fn get<R: Runtime>(device: &R::Device) -> Arc<Self> {
+        Self::get_with_client::<R>(&R::client(device), device)
+    }
+
+    pub(crate) fn get_with_client<R: Runtime>(
+        client: &ComputeClient<R::Server>,
+        device: &R::Device,
+    ) -> Arc<Self> {
+        let id = CubeTuneId::new::<R>(client, device);
+        let mut settings = SETTINGS.lock().unwrap();
+
+        settings
+            .get_or_insert_with(HashMap::new)
+            .entry(id)
+            .or_default()
+            .clone()
+    }
+
+    /// The [options](MatmulFusionOptions) of the fused matmuls.
+    pub fn options(&self) -> MatmulFusionOptions {
+        *self.options.lock().unwrap()
+    }
+
+    /// Set the [options](MatmulFusionOptions) of the fused matmuls.
+    ///
+    /// Optimizations already built are updated on their next execution.
+    pub fn set_options(&self, options: MatmulFusionOptions) {
+        *self.options.lock().unwrap() = options;
+    }
+
+    /// Disable the fused kernels at runtime, so that every matmul optimization of the device runs
+    /// the unfused matmul followed by the element-wise trace.
+    pub fn set_fusion_disabled(&self, disabled: bool) {
+        self.fusion_disabled.store(disabled, Ordering::Relaxed);
+    }
+
+    /// Whether the fused kernels are [disabled](Self::set_fusion_disabled).
+    pub fn fusion_disabled(&self) -> bool {
+        self.fusion_disabled.load(Ordering::Relaxed)
+    }
+
+    /// Report every autotune candidate whose outputs differ from the reference by more than the
+    /// given maximum absolute difference, or stop reporting with `None`.
+    ///
+    /// Only used with the `autotune-checks` feature, where the outputs of every candidate are
+    /// compared.
+    pub fn set_divergence_threshold(&self, threshold: Option<f64>) {
+        *self.divergence_threshold.lock().unwrap() = threshold;
+    }
+
+    /// The [divergence threshold](Self::set_divergence_threshold), if any.
+    pub fn divergence_threshold(&self) -> Option<f64> {
+        *self.divergence_threshold.lock().unwrap()
+    }
+
+    /// Set a hook invoked after each execution with the [profile](MatmulProfile) of the kernel
+    /// that was selected.
+    ///
+    /// Executions done while autotuning candidates aren't reported.
+    pub fn set_profiling_hook(&self, hook: impl Fn(&MatmulProfile) + Send + Sync + 'static) {
+        *self.profiling_hook.lock().unwrap() = Some(Arc::new(hook));
+    }
+
+    /// Remove the [profiling hook](Self::set_profiling_hook), if any.
+    pub fn clear_profiling_hook(&self) {
+        *self.profiling_hook.lock().unwrap() = None;
+    }
+
+    pub(crate) fn profiling_hook(&self) -> Option<MatmulProfilingHook> {
+        self.profiling_hook.lock().unwrap().clone()
+    }
+
+    /// The number of executions per path taken since the first execution on the device or since
+    /// the last [reset](Self::reset_stats).
+    pub fn stats(&self) -> FusionStats {
+        FusionStats {
+            fused_count: self.counters.fused.load(Ordering::Relaxed),
+            fallback_count: self.counters.fallback.load(Ordering::Relaxed),
+            invalid_input_count: self.counters.invalid_input.load(Ordering::Relaxed),
+            repack_count: self.counters.repacked.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Reset the [stats](Self::stats), e.g. between epochs.
+    pub fn reset_stats(&self) {
+        self.counters.fused.store(0, Ordering::Relaxed);
+        self.counters.fallback.store(0, Ordering::Relaxed);
+        self.counters.invalid_input.store(0, Ordering::Relaxed);
+        self.counters.repacked.store(0, Ordering::Relaxed);
+    }
+
+    pub(crate) fn counters(&self) -> &FusionCounters {
+        &self.counters
+    }
+}
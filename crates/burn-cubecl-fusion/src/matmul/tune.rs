@@ -21,11 +21,22 @@ use cubecl::{
 };
 use serde::{Deserialize, Serialize};
 
-use super::optimization::{MatmulOptimizationTuneArg, MatmulVariantSelection};
+use super::optimization::{
+    AccumulationPrecision, MatmulOptimizationTuneArg, MatmulVariantSelection,
+};
 
+/// Autotune key of the [fused matmul](fused_matmul_autotune).
+///
+/// The key is serializable so the tuning results can be persisted across processes by the cubecl
+/// tuner cache. That cache is scoped to the device through [CubeTuneId] and is invalidated when
+/// the set of tunables changes.
 #[derive(Hash, Eq, PartialEq, Debug, Clone, Serialize, Deserialize, AutotuneKey)]
 pub struct FusedMatmulAutotuneKey {
     matmul_key: MatmulAutotuneKey,
+    /// The repack copy is included in the measurements, so it can change the fastest kernel.
+    repack: bool,
+    /// Accumulating in a lower precision changes the register pressure of every kernel.
+    accumulation: AccumulationPrecision,
     #[autotune(anchor)]
     num_out_buffers: usize,
     #[autotune(anchor)]
@@ -177,7 +188,13 @@ pub(crate) fn create_key<R: Runtime>(
             quantized: matches!(out.dtype, DType::QFloat(_)),
         },
     );
-    FusedMatmulAutotuneKey::new(key, opt.info.num_output_buffers(), opt.info.num_ops_fused())
+    FusedMatmulAutotuneKey::new(
+        key,
+        opt.info.variants.simple.repack,
+        opt.info.variants.simple.accumulation,
+        opt.info.num_output_buffers(),
+        opt.info.num_ops_fused(),
+    )
 }
 
 fn input_gen<R: Runtime>(
@@ -195,8 +212,13 @@ fn tune_fused<R: Runtime, BT: CubeElement, S: MatmulVariantSelection>(
 
     match context {
         TuneContext::Original(context) => match optimization.execute_fused::<BT, S>(context) {
-            Ok(out) => Ok(out),
-            Err(_) => {
+            Ok(out) => {
+                let selector = S::select(&optimization.info.variants).selector.clone();
+                optimization.profile(context, Some(selector));
+                Ok(out)
+            }
+            Err(err) => {
+                optimization.record_error(&err);
                 return tune_fallback::<R, BT>(input);
             }
         },
@@ -214,7 +236,11 @@ fn tune_fallback<R: Runtime, BT: CubeElement>(
     let context = input.context();
 
     Ok(match context {
-        TuneContext::Original(context) => optimization.execute_fallback::<BT>(context),
+        TuneContext::Original(context) => {
+            let output = optimization.execute_fallback::<BT>(context);
+            optimization.profile(context, None);
+            output
+        }
         TuneContext::Fork(mut context_owned) => {
             optimization.execute_fallback::<BT>(&mut context_owned.as_context())
         }
@@ -142,39 +142,44 @@ pub struct QuantSchemeFuse {
 }
 
 impl FuseOp {
-    /// Element type used for the computation.
-    pub(crate) fn cmp_elem(&self) -> ElemType {
+    /// Precision used for the computation.
+    pub(crate) fn cmp_precision(&self) -> FusePrecision {
         match self {
-            FuseOp::Add(op) => op.lhs.precision().into_elem(),
-            FuseOp::Sub(op) => op.lhs.precision().into_elem(),
-            FuseOp::Mul(op) => op.lhs.precision().into_elem(),
-            FuseOp::Div(op) => op.lhs.precision().into_elem(),
-            FuseOp::Powf(op) => op.lhs.precision().into_elem(),
-            FuseOp::Abs(op) => op.out.precision().into_elem(),
-            FuseOp::Exp(op) => op.out.precision().into_elem(),
-            FuseOp::Log(op) => op.out.precision().into_elem(),
-            FuseOp::Log1p(op) => op.out.precision().into_elem(),
-            FuseOp::Cos(op) => op.out.precision().into_elem(),
-            FuseOp::Sin(op) => op.out.precision().into_elem(),
-            FuseOp::Tanh(op) => op.out.precision().into_elem(),
-            FuseOp::Erf(op) => op.out.precision().into_elem(),
-            FuseOp::Recip(op) => op.out.precision().into_elem(),
-            FuseOp::Sqrt(op) => op.out.precision().into_elem(),
-            FuseOp::Assign(op) => op.out.precision().into_elem(),
-            FuseOp::Equal(op) => op.lhs.precision().into_elem(),
-            FuseOp::Lower(op) => op.lhs.precision().into_elem(),
-            FuseOp::Greater(op) => op.lhs.precision().into_elem(),
-            FuseOp::LowerEqual(op) => op.lhs.precision().into_elem(),
-            FuseOp::GreaterEqual(op) => op.lhs.precision().into_elem(),
-            FuseOp::ConditionalAssign { out, .. } => out.precision().into_elem(),
-            FuseOp::Gather { output, .. } => output.precision().into_elem(),
-            FuseOp::Select { output, .. } => output.precision().into_elem(),
-            FuseOp::Dequantize { output, .. } => output.precision().into_elem(),
-            FuseOp::Rem(op) => op.out.precision().into_elem(),
-            FuseOp::Clamp { out, .. } => out.precision().into_elem(),
+            FuseOp::Add(op) => op.lhs.precision(),
+            FuseOp::Sub(op) => op.lhs.precision(),
+            FuseOp::Mul(op) => op.lhs.precision(),
+            FuseOp::Div(op) => op.lhs.precision(),
+            FuseOp::Powf(op) => op.lhs.precision(),
+            FuseOp::Abs(op) => op.out.precision(),
+            FuseOp::Exp(op) => op.out.precision(),
+            FuseOp::Log(op) => op.out.precision(),
+            FuseOp::Log1p(op) => op.out.precision(),
+            FuseOp::Cos(op) => op.out.precision(),
+            FuseOp::Sin(op) => op.out.precision(),
+            FuseOp::Tanh(op) => op.out.precision(),
+            FuseOp::Erf(op) => op.out.precision(),
+            FuseOp::Recip(op) => op.out.precision(),
+            FuseOp::Sqrt(op) => op.out.precision(),
+            FuseOp::Assign(op) => op.out.precision(),
+            FuseOp::Equal(op) => op.lhs.precision(),
+            FuseOp::Lower(op) => op.lhs.precision(),
+            FuseOp::Greater(op) => op.lhs.precision(),
+            FuseOp::LowerEqual(op) => op.lhs.precision(),
+            FuseOp::GreaterEqual(op) => op.lhs.precision(),
+            FuseOp::ConditionalAssign { out, .. } => out.precision(),
+            FuseOp::Gather { output, .. } => output.precision(),
+            FuseOp::Select { output, .. } => output.precision(),
+            FuseOp::Dequantize { output, .. } => output.precision(),
+            FuseOp::Rem(op) => op.out.precision(),
+            FuseOp::Clamp { out, .. } => out.precision(),
         }
     }
 
+    /// Element type used for the computation.
+    pub(crate) fn cmp_elem(&self) -> ElemType {
+        self.cmp_precision().into_elem()
+    }
+
     /// Element type used for the computation.
     pub(crate) fn cmp_type(&self) -> StorageType {
         self.cmp_elem().into()
@@ -60,13 +60,91 @@ impl<R: Runtime> TuneOutput<R> {
 
         result
     }
+
+    /// Compute the [divergence](OutputDivergence) of every output written by both executions.
+    ///
+    /// Outputs only present in one of them are skipped, and unchecked outputs have nothing to
+    /// compare.
+    #[cfg(feature = "autotune-checks")]
+    pub(crate) fn compare(&self, other: &Self) -> Vec<(TensorId, OutputDivergence)> {
+        let (
+            TuneOutput::Checked { handles },
+            TuneOutput::Checked {
+                handles: handles_other,
+            },
+        ) = (self, other)
+        else {
+            return Vec::new();
+        };
+
+        let mut divergences = Vec::new();
+
+        for (id, (shape, handle)) in handles.iter() {
+            let Some((shape_other, other)) = handles_other.get(id) else {
+                continue;
+            };
+
+            let data = handle.client.read_one(handle.handle.clone());
+            let data_other = other.client.read_one(other.handle.clone());
+            let data = TensorData::from_bytes(data, shape.clone(), handle.dtype);
+            let data_other = TensorData::from_bytes(data_other, shape_other.clone(), other.dtype);
+
+            let mut max_abs = 0.0f64;
+            let mut sum_abs = 0.0f64;
+            let mut num_elems = 0;
+
+            for (a, b) in data.iter::<f64>().zip(data_other.iter::<f64>()) {
+                let diff = (a - b).abs();
+                max_abs = max_abs.max(diff);
+                sum_abs += diff;
+                num_elems += 1;
+            }
+
+            let mean_abs = match num_elems {
+                0 => 0.0,
+                _ => sum_abs / num_elems as f64,
+            };
+
+            divergences.push((*id, OutputDivergence { max_abs, mean_abs }));
+        }
+
+        divergences
+    }
+}
+
+/// Numerical difference between the same output written by two executions.
+#[cfg(feature = "autotune-checks")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct OutputDivergence {
+    /// The maximum absolute difference between two values.
+    pub max_abs: f64,
+    /// The mean absolute difference over all values.
+    pub mean_abs: f64,
 }
 
 impl<R: Runtime> cubecl::tune::AutotuneOutput for TuneOutput<R> {
     #[cfg(feature = "autotune-checks")]
     fn check_equivalence(&self, other: Self) {
+        use crate::matmul::settings::MatmulFusionSettings;
         use burn_tensor::{DType, Tolerance};
 
+        if let TuneOutput::Checked { handles } = self
+            && let Some((_, handle)) = handles.values().next()
+            && let Some(threshold) =
+                MatmulFusionSettings::get_with_client::<R>(&handle.client, &handle.device)
+                    .divergence_threshold()
+        {
+            for (id, divergence) in self.compare(&other) {
+                if divergence.max_abs > threshold {
+                    println!(
+                        "Output {id:?} diverges from the reference above the threshold of \
+                         {threshold}: max abs {}, mean abs {}",
+                        divergence.max_abs, divergence.mean_abs,
+                    );
+                }
+            }
+        }
+
         if let (
             TuneOutput::Checked {
                 handles: handles_ref,
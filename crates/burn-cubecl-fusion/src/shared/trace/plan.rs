@@ -109,6 +109,29 @@ impl<R: Runtime> LaunchPlan<'_, R> {
             cleared: Default::default(),
         }
     }
+
+    /// The global id of the tensor whose layout is used as the reference of the given block, when
+    /// the reference is a concrete input or output.
+    pub fn reference_tensor(&self, block: usize) -> Option<TensorId> {
+        match &self.blocks.get(block)?.reference {
+            ReferenceSelection::Concrete {
+                layout: Arg::Input(pos, ..),
+                ..
+            } => self
+                .handle_inputs
+                .get(*pos as usize)?
+                .as_normal()
+                .map(|handle| handle.global_ir.id),
+            ReferenceSelection::Concrete {
+                layout: Arg::Output(pos, ..),
+                ..
+            } => self
+                .global_outputs
+                .get(*pos as usize)
+                .map(|tensor| tensor.id),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(feature = "autotune-checks")]
@@ -65,6 +65,9 @@ pub trait Vectorization<R: Runtime> {
         VectorizationAxis::default()
     }
     /// The vectorization factor for all inputs and outputs.
+    ///
+    /// The reference is the global id of the tensor whose layout is used as the reference of
+    /// the first block, when it's a concrete input or output.
     #[allow(clippy::too_many_arguments)]
     fn vectorization<'a>(
         &self,
@@ -77,6 +80,7 @@ pub trait Vectorization<R: Runtime> {
         line_sizes: &[u8],
         max: u8,
         axis: VectorizationAxis,
+        _reference: Option<TensorId>,
     ) {
         vectorization_default(
             vectorizations,
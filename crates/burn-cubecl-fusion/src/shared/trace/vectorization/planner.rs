@@ -126,6 +126,7 @@ impl<'a, R: Runtime> VectorizationPlanner<'a, R> {
         };
 
         let vectorization_axis = runner.axis(plan);
+        let reference = plan.reference_tensor(0);
 
         runner.vectorization(
             context,
@@ -156,6 +157,7 @@ impl<'a, R: Runtime> VectorizationPlanner<'a, R> {
             &line_sizes,
             u8::MAX,
             vectorization_axis,
+            reference,
         );
 
         for tensor in self.resources.indexed.keys() {
@@ -18,6 +18,14 @@ use core::marker::PhantomData;
 use half::{bf16, f16};
 use std::sync::Arc;
 
+pub use burn_cubecl_fusion::FusePrecision;
+pub use burn_cubecl_fusion::matmul::optimization::{
+    AccumulationPrecision, FusedMatmulSelector, FusedMatmulSummary, supported_matmul_precisions,
+};
+pub use burn_cubecl_fusion::matmul::settings::{
+    FusionStats, MatmulFusionOptions, MatmulFusionSettings, MatmulProfile, MatmulProfilingHook,
+};
+
 impl<R, BT> burn_fusion::Optimization<FusionCubeRuntime<R, BT>> for CubeOptimization<R>
 where
     R: CubeRuntime,
@@ -178,6 +178,14 @@ where
     }
 
     fn float_matmul(lhs: FloatTensor<Self>, rhs: FloatTensor<Self>) -> FloatTensor<Self> {
+        // The matmul kernels read both operands with the same element type, so mixed precision
+        // operands are computed in the precision of the lhs, which is also the output precision.
+        let rhs = if rhs.dtype != lhs.dtype {
+            Self::float_cast(rhs, lhs.dtype.into())
+        } else {
+            rhs
+        };
+
         execute_with_dtype!(
             float(lhs.dtype, rhs.dtype),
             E,
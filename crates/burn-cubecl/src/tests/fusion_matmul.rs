@@ -0,0 +1,788 @@
+#[burn_tensor_testgen::testgen(fusion_matmul)]
+mod tests {
+    use super::*;
+    use burn_cubecl::cubecl::Runtime;
+    use burn_cubecl::fusion::{
+        AccumulationPrecision, FusePrecision, FusedMatmulSelector, MatmulFusionOptions,
+        MatmulFusionSettings, MatmulProfile, supported_matmul_precisions,
+    };
+    use burn_tensor::{
+        DType, Distribution, FloatDType, TensorData, Tolerance, backend::Backend, ops::FloatElem,
+    };
+    use serial_test::serial;
+    use std::sync::{Arc, Mutex};
+
+    type FT = FloatElem<TestBackend>;
+
+    fn settings() -> Arc<MatmulFusionSettings> {
+        MatmulFusionSettings::get::<TestRuntime>(&Default::default())
+    }
+
+    /// Record the profiles of every fused matmul executed until the hook is cleared.
+    fn record_profiles(settings: &MatmulFusionSettings) -> Arc<Mutex<Vec<MatmulProfile>>> {
+        let profiles = Arc::new(Mutex::new(Vec::new()));
+        let recorded = profiles.clone();
+        settings.set_profiling_hook(move |profile| recorded.lock().unwrap().push(profile.clone()));
+        profiles
+    }
+
+    /// The profiles of the fused matmuls executed with the given shapes.
+    fn profiles_of(
+        profiles: &Mutex<Vec<MatmulProfile>>,
+        lhs_shape: &[usize],
+        rhs_shape: &[usize],
+    ) -> Vec<MatmulProfile> {
+        profiles
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|profile| profile.lhs_shape == lhs_shape && profile.rhs_shape == rhs_shape)
+            .cloned()
+            .collect()
+    }
+
+    /// Run the function in deterministic mode, asserting that every matmul with the given shapes
+    /// was executed with a fused kernel.
+    fn assert_fused<T>(lhs_shape: &[usize], rhs_shape: &[usize], func: impl FnOnce() -> T) -> T {
+        let settings = settings();
+        let profiles = record_profiles(&settings);
+        settings.set_options(MatmulFusionOptions {
+            deterministic: true,
+            ..Default::default()
+        });
+
+        let output = func();
+
+        settings.set_options(Default::default());
+        settings.clear_profiling_hook();
+
+        let profiles = profiles_of(&profiles, lhs_shape, rhs_shape);
+        assert!(
+            !profiles.is_empty(),
+            "No fused matmul executed for {lhs_shape:?} @ {rhs_shape:?}"
+        );
+        assert!(
+            profiles.iter().all(|profile| profile.selector.is_some()),
+            "The fused matmul fell back for {lhs_shape:?} @ {rhs_shape:?}"
+        );
+
+        output
+    }
+
+    #[test]
+    #[serial]
+    fn should_count_fused_matmul_executions() {
+        let device = Default::default();
+        let settings = settings();
+        let before = settings.stats();
+
+        let lhs = TestTensor::<2>::from_floats([[1., 2.], [3., 4.]], &device);
+        let rhs = TestTensor::from_floats([[5., 6.], [7., 8.]], &device);
+
+        let out = assert_fused(&[2, 2], &[2, 2], || {
+            TestBackend::sync(&device);
+            (lhs.matmul(rhs) + 1).into_data()
+        });
+
+        out.assert_eq(&TensorData::from([[20., 23.], [44., 51.]]), false);
+        // Other suites may run matmuls on the same device, so only the fused count can be checked
+        // globally, while the profile of this execution shows it didn't fall back.
+        assert!(settings.stats().fused_count > before.fused_count);
+    }
+
+    #[test]
+    #[serial]
+    fn should_count_invalid_input_fallbacks() {
+        let device = Default::default();
+        let settings = settings();
+        settings.reset_stats();
+
+        // Swapping a batch dimension with a matrix dimension gives a highly permuted layout.
+        let lhs =
+            TestTensor::<3>::from_floats([[[1., 2.], [3., 4.]], [[5., 6.], [7., 8.]]], &device)
+                .swap_dims(0, 2);
+        let rhs = TestTensor::from_floats([[[1., 0.], [0., 1.]], [[1., 0.], [0., 1.]]], &device);
+
+        TestBackend::sync(&device);
+        let out = lhs.matmul(rhs) + 1;
+
+        out.into_data().assert_eq(
+            &TensorData::from([[[2., 6.], [4., 8.]], [[3., 7.], [5., 9.]]]),
+            false,
+        );
+
+        assert!(settings.stats().invalid_input_count >= 1);
+    }
+
+    #[test]
+    #[serial]
+    fn should_report_profiles_to_the_hook() {
+        let device = Default::default();
+        let settings = settings();
+        let profiles = record_profiles(&settings);
+
+        let lhs = TestTensor::<2>::ones([3, 5], &device);
+        let rhs = TestTensor::ones([5, 7], &device);
+
+        TestBackend::sync(&device);
+        let out = lhs.matmul(rhs) + 1;
+
+        out.into_data()
+            .assert_eq(&TensorData::from([[6.; 7]; 3]), false);
+        settings.clear_profiling_hook();
+
+        assert_eq!(profiles_of(&profiles, &[3, 5], &[5, 7]).len(), 1);
+    }
+
+    #[test]
+    #[serial]
+    fn should_run_unfused_when_fusion_is_disabled() {
+        let device = Default::default();
+        let settings = settings();
+        let profiles = record_profiles(&settings);
+        settings.set_fusion_disabled(true);
+
+        let lhs = TestTensor::<2>::ones([3, 6], &device);
+        let rhs = TestTensor::ones([6, 7], &device);
+
+        TestBackend::sync(&device);
+        let out = lhs.matmul(rhs) + 1;
+
+        out.into_data()
+            .assert_eq(&TensorData::from([[7.; 7]; 3]), false);
+        settings.set_fusion_disabled(false);
+        settings.clear_profiling_hook();
+
+        let profiles = profiles_of(&profiles, &[3, 6], &[6, 7]);
+        assert!(!profiles.is_empty());
+        assert!(profiles.iter().all(|profile| profile.selector.is_none()));
+    }
+
+    #[test]
+    #[serial]
+    fn should_give_identical_bytes_on_deterministic_runs() {
+        let device = Default::default();
+        let settings = settings();
+        let profiles = record_profiles(&settings);
+        settings.set_options(MatmulFusionOptions {
+            deterministic: true,
+            ..Default::default()
+        });
+
+        let lhs = TestTensor::<2>::random([64, 96], Distribution::Default, &device);
+        let rhs = TestTensor::random([96, 80], Distribution::Default, &device);
+
+        let run = || {
+            TestBackend::sync(&device);
+            let out = lhs.clone().matmul(rhs.clone()) + 1;
+            out.into_data()
+        };
+        let first = run();
+        let second = run();
+
+        settings.set_options(Default::default());
+        settings.clear_profiling_hook();
+
+        assert_eq!(first.as_bytes(), second.as_bytes());
+        let profiles = profiles_of(&profiles, &[64, 96], &[96, 80]);
+        assert_eq!(profiles.len(), 2);
+        assert!(profiles.iter().all(|profile| profile.selector.is_some()));
+    }
+
+    #[test]
+    #[serial]
+    fn should_fuse_highly_permuted_inputs_with_repack() {
+        let device = Default::default();
+        let settings = settings();
+        let profiles = record_profiles(&settings);
+        // Deterministic so the fused kernel is selected without autotune picking the fallback.
+        settings.set_options(MatmulFusionOptions {
+            deterministic: true,
+            repack: true,
+            ..Default::default()
+        });
+
+        let ref_device = Default::default();
+        let lhs_ref =
+            ReferenceTensor::<3>::random([6, 5, 4], Distribution::Uniform(-1.0, 1.0), &ref_device);
+        let rhs_ref =
+            ReferenceTensor::<3>::random([6, 4, 3], Distribution::Uniform(-1.0, 1.0), &ref_device);
+
+        // Swapping a batch dimension with a matrix dimension gives a highly permuted layout.
+        let lhs = TestTensor::<3>::from_data(lhs_ref.to_data(), &device).swap_dims(0, 2);
+        let rhs = TestTensor::<3>::from_data(rhs_ref.to_data(), &device).swap_dims(0, 1);
+        let lhs_ref = lhs_ref.swap_dims(0, 2);
+        let rhs_ref = rhs_ref.swap_dims(0, 1);
+
+        TestBackend::sync(&device);
+        let out = lhs.clone().matmul(rhs) + 1;
+        // The repacked copy replaces the handle of the input, which must keep its values.
+        let reused = lhs * 2;
+
+        let out = out.into_data();
+        let reused = reused.into_data();
+        settings.set_options(Default::default());
+        settings.clear_profiling_hook();
+
+        (lhs_ref.clone().matmul(rhs_ref) + 1)
+            .into_data()
+            .assert_approx_eq::<FT>(&out, Tolerance::default());
+        (lhs_ref * 2)
+            .into_data()
+            .assert_approx_eq::<FT>(&reused, Tolerance::default());
+
+        let profiles = profiles_of(&profiles, &[4, 5, 6], &[4, 6, 3]);
+        assert_eq!(profiles.len(), 1);
+        assert!(profiles[0].selector.is_some());
+    }
+
+    #[test]
+    #[serial]
+    fn should_reuse_the_repacked_copy_of_a_persistent_input() {
+        let device = Default::default();
+        let settings = settings();
+        let profiles = record_profiles(&settings);
+        settings.set_options(MatmulFusionOptions {
+            deterministic: true,
+            repack: true,
+            ..Default::default()
+        });
+
+        // Kept alive across executions, like a weight.
+        let lhs =
+            TestTensor::<3>::random([7, 5, 2], Distribution::Default, &device).swap_dims(0, 2);
+
+        for _ in 0..3 {
+            let rhs = TestTensor::<3>::random([2, 7, 3], Distribution::Default, &device);
+
+            TestBackend::sync(&device);
+            let out = lhs.clone().matmul(rhs) + 1;
+            out.into_data();
+        }
+        settings.set_options(Default::default());
+        settings.clear_profiling_hook();
+
+        let profiles = profiles_of(&profiles, &[2, 5, 7], &[2, 7, 3]);
+        let repack_counts: Vec<_> = profiles.iter().map(|p| p.repack_count).collect();
+        let cached_repacks: Vec<_> = profiles.iter().map(|p| p.cached_repacks).collect();
+
+        // Copied by the first two executions, cached once reused, then reused by the third one.
+        assert_eq!(repack_counts, [1, 1, 0]);
+        assert_eq!(cached_repacks, [0, 1, 1]);
+    }
+
+    #[test]
+    #[serial]
+    fn should_drop_the_repacked_copy_of_a_dropped_input() {
+        let device = Default::default();
+        let settings = settings();
+        let profiles = record_profiles(&settings);
+        settings.set_options(MatmulFusionOptions {
+            deterministic: true,
+            repack: true,
+            ..Default::default()
+        });
+
+        let run = |lhs: &TestTensor<3>| {
+            let rhs = TestTensor::<3>::random([3, 7, 2], Distribution::Default, &device);
+
+            TestBackend::sync(&device);
+            let out = lhs.clone().matmul(rhs) + 1;
+            out.into_data();
+        };
+
+        let lhs =
+            TestTensor::<3>::random([7, 4, 3], Distribution::Default, &device).swap_dims(0, 2);
+        run(&lhs);
+        run(&lhs);
+        drop(lhs);
+
+        let lhs =
+            TestTensor::<3>::random([7, 4, 3], Distribution::Default, &device).swap_dims(0, 2);
+        run(&lhs);
+
+        settings.set_options(Default::default());
+        settings.clear_profiling_hook();
+
+        let profiles = profiles_of(&profiles, &[3, 4, 7], &[3, 7, 2]);
+        let cached_repacks: Vec<_> = profiles.iter().map(|p| p.cached_repacks).collect();
+
+        // The copy of the first lhs is removed once it's dropped, and the new lhs isn't cached
+        // before it's reused.
+        assert_eq!(cached_repacks, [0, 1, 0]);
+    }
+
+    #[test]
+    fn should_always_support_f32_matmul_fusion() {
+        let client = TestRuntime::client(&Default::default());
+        let precisions = supported_matmul_precisions::<TestRuntime>(&client);
+
+        assert!(precisions.contains(&FusePrecision::F32));
+        assert!(precisions.contains(&FusePrecision::Flex32));
+    }
+
+    #[test]
+    #[serial]
+    fn should_describe_the_fused_trace() {
+        let device = Default::default();
+        let settings = settings();
+        let profiles = record_profiles(&settings);
+
+        let lhs = TestTensor::<2>::ones([5, 3], &device);
+        let rhs = TestTensor::ones([3, 6], &device);
+
+        TestBackend::sync(&device);
+        let out = lhs.matmul(rhs) + 1;
+
+        out.into_data()
+            .assert_eq(&TensorData::from([[4.; 6]; 5]), false);
+        settings.clear_profiling_hook();
+
+        let profiles = profiles_of(&profiles, &[5, 3], &[3, 6]);
+        assert_eq!(profiles.len(), 1);
+        let summary = &profiles[0].summary;
+        // The matmul and the addition.
+        assert_eq!(summary.num_ops, 2);
+        assert_eq!(summary.precisions, [FusePrecision::F32]);
+        assert!(summary.has_fallback);
+        assert!(
+            summary
+                .to_string()
+                .starts_with("FusedMatmul { ops: 2, output_buffers: ")
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn should_accumulate_half_precision_in_the_output_precision() {
+        let device = Default::default();
+        let client = TestRuntime::client(&device);
+        if !supported_matmul_precisions::<TestRuntime>(&client).contains(&FusePrecision::F16) {
+            return;
+        }
+
+        let settings = settings();
+        let profiles = record_profiles(&settings);
+        settings.set_options(MatmulFusionOptions {
+            deterministic: true,
+            accumulation: AccumulationPrecision::Output,
+            ..Default::default()
+        });
+
+        let ref_device = Default::default();
+        let lhs_ref =
+            ReferenceTensor::<2>::random([16, 32], Distribution::Uniform(-1.0, 1.0), &ref_device);
+        let rhs_ref =
+            ReferenceTensor::<2>::random([32, 24], Distribution::Uniform(-1.0, 1.0), &ref_device);
+
+        let lhs = TestTensor::<2>::from_data(lhs_ref.to_data(), &device).cast(FloatDType::F16);
+        let rhs = TestTensor::<2>::from_data(rhs_ref.to_data(), &device).cast(FloatDType::F16);
+
+        TestBackend::sync(&device);
+        let out = (lhs.matmul(rhs) + 1).cast(FloatDType::F32).into_data();
+
+        settings.set_options(Default::default());
+        settings.clear_profiling_hook();
+
+        (lhs_ref.matmul(rhs_ref) + 1)
+            .into_data()
+            .assert_approx_eq::<FT>(&out, Tolerance::rel_abs(5e-2, 5e-2));
+        let profiles = profiles_of(&profiles, &[16, 32], &[32, 24]);
+        assert_eq!(profiles.len(), 1);
+        assert!(profiles[0].selector.is_some());
+    }
+
+    #[test]
+    #[serial]
+    fn should_fuse_when_the_output_can_not_be_vectorized() {
+        let device = Default::default();
+        let settings = settings();
+        let profiles = record_profiles(&settings);
+        settings.set_options(MatmulFusionOptions {
+            deterministic: true,
+            ..Default::default()
+        });
+
+        let ref_device = Default::default();
+        let lhs_ref =
+            ReferenceTensor::<2>::random([8, 4], Distribution::Uniform(-1.0, 1.0), &ref_device);
+        let rhs_ref =
+            ReferenceTensor::<2>::random([4, 3], Distribution::Uniform(-1.0, 1.0), &ref_device);
+
+        // The lhs rows can be read with lines of 4, but the output rows have an odd length.
+        let lhs = TestTensor::<2>::from_data(lhs_ref.to_data(), &device);
+        let rhs = TestTensor::<2>::from_data(rhs_ref.to_data(), &device);
+
+        TestBackend::sync(&device);
+        let out = (lhs.matmul(rhs) + 1).into_data();
+
+        settings.set_options(Default::default());
+        settings.clear_profiling_hook();
+
+        (lhs_ref.matmul(rhs_ref) + 1)
+            .into_data()
+            .assert_approx_eq::<FT>(&out, Tolerance::default());
+        let profiles = profiles_of(&profiles, &[8, 4], &[4, 3]);
+        assert_eq!(profiles.len(), 1);
+        assert!(profiles[0].selector.is_some());
+    }
+
+    #[test]
+    #[serial]
+    fn should_fuse_affine_epilogue() {
+        let device = Default::default();
+        let lhs = TestTensor::<2>::from_floats([[1., 2., 3.], [4., 5., 6.]], &device);
+        let rhs = TestTensor::from_floats(
+            [[1., 0., 2., 1.], [0., 1., 1., 2.], [1., 1., 0., 1.]],
+            &device,
+        );
+        // Per-output-channel operands, broadcast over the rows of the output.
+        let scale = TestTensor::from_floats([[1., 2., 0.5, -1.]], &device);
+        let bias = TestTensor::from_floats([[0.5, -1., 2., 3.]], &device);
+
+        let out = assert_fused(&[2, 3], &[3, 4], || {
+            TestBackend::sync(&device);
+            (lhs.matmul(rhs) * scale + bias).into_data()
+        });
+
+        out.assert_approx_eq::<FT>(
+            &TensorData::from([[4.5, 9., 4., -5.], [10.5, 21., 8.5, -17.]]),
+            Tolerance::default(),
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn should_fuse_attention_scale() {
+        let device = Default::default();
+        let d_k = 4;
+        let q = TestTensor::<3>::from_floats([[[1., 2., 3., 4.], [0., 1., 0., 1.]]], &device);
+        let k = TestTensor::from_floats(
+            [[[1., 0., 0., 0.], [0., 1., 0., 1.], [2., 2., 2., 2.]]],
+            &device,
+        );
+
+        let logits = assert_fused(&[1, 2, 4], &[1, 4, 3], || {
+            TestBackend::sync(&device);
+            q.matmul(k.transpose())
+                .div_scalar((d_k as f32).sqrt())
+                .into_data()
+        });
+
+        logits.assert_approx_eq::<FT>(
+            &TensorData::from([[[0.5, 3., 10.], [0., 1., 2.]]]),
+            Tolerance::default(),
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn should_fuse_activation() {
+        let device = Default::default();
+        let lhs = TestTensor::<2>::from_floats([[1., -2., 0.5], [3., -4., 1.]], &device);
+        let rhs = TestTensor::from_floats([[1., 2.], [3., -1.], [2., 2.]], &device);
+
+        let out = assert_fused(&[2, 3], &[3, 2], || {
+            TestBackend::sync(&device);
+            burn_tensor::activation::relu(lhs.matmul(rhs)).into_data()
+        });
+
+        out.assert_eq(&TensorData::from([[0., 5.], [0., 12.]]), false);
+    }
+
+    #[test]
+    #[serial]
+    fn should_fuse_alpha_beta_accumulation_inplace() {
+        let device = Default::default();
+        let a = TestTensor::<2>::from_floats([[1., 2., 0.], [0., 1., 3.]], &device);
+        let b = TestTensor::from_floats([[1., 0.], [2., 1.], [0., 1.]], &device);
+        let c = TestTensor::from_floats([[2., 4.], [6., 8.]], &device);
+        let (alpha, beta) = (2.0, 0.5);
+
+        let c = assert_fused(&[2, 3], &[3, 2], || {
+            TestBackend::sync(&device);
+            // C is at its last use, so its buffer can be reused for the output.
+            let c = a.matmul(b) * alpha + c * beta;
+            c.into_data()
+        });
+
+        c.assert_approx_eq::<FT>(
+            &TensorData::from([[11., 6.], [7., 12.]]),
+            Tolerance::default(),
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn should_fuse_ops_on_transposed_output() {
+        let device = Default::default();
+        let lhs = TestTensor::<2>::from_floats([[1., 2.], [3., 4.], [5., 6.]], &device);
+        let rhs = TestTensor::from_floats([[1., 0., 1.], [0., 1., 1.]], &device);
+
+        let out = assert_fused(&[3, 2], &[2, 3], || {
+            TestBackend::sync(&device);
+            (lhs.matmul(rhs).transpose() + 1).into_data()
+        });
+
+        out.assert_eq(
+            &TensorData::from([[2., 4., 6.], [3., 5., 7.], [4., 8., 12.]]),
+            false,
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn should_fuse_matmul_with_transposed_output_feeding_a_matmul() {
+        let device = Default::default();
+        let lhs = TestTensor::<2>::from_floats([[1., 2.], [3., 4.], [5., 6.]], &device);
+        let rhs = TestTensor::from_floats([[1., 0., 1.], [0., 1., 1.]], &device);
+        let next = TestTensor::from_floats([[1., 0.], [0., 1.], [1., 1.]], &device);
+
+        let out = assert_fused(&[3, 2], &[2, 3], || {
+            TestBackend::sync(&device);
+            (lhs.matmul(rhs) + 0).transpose().matmul(next).into_data()
+        });
+
+        out.assert_eq(&TensorData::from([[6., 8.], [8., 10.], [14., 18.]]), false);
+    }
+
+    #[test]
+    #[serial]
+    fn should_fuse_f32_epilogue() {
+        let device = Default::default();
+        let client = TestRuntime::client(&device);
+        if !supported_matmul_precisions::<TestRuntime>(&client).contains(&FusePrecision::F16) {
+            return;
+        }
+
+        let ref_device = Default::default();
+        let lhs_ref =
+            ReferenceTensor::<2>::random([3, 5], Distribution::Uniform(-1.0, 1.0), &ref_device);
+        let rhs_ref =
+            ReferenceTensor::<2>::random([5, 2], Distribution::Uniform(-1.0, 1.0), &ref_device);
+        let lhs = TestTensor::<2>::from_data(lhs_ref.to_data(), &device).cast(FloatDType::F16);
+        let rhs = TestTensor::<2>::from_data(rhs_ref.to_data(), &device).cast(FloatDType::F16);
+
+        let out = assert_fused(&[3, 5], &[5, 2], || {
+            TestBackend::sync(&device);
+            // The matmul runs in f16, the element-wise ops in f32 between the casts.
+            let out = lhs.matmul(rhs).cast(FloatDType::F32);
+            let exp = out.exp();
+            (exp.clone() / (exp + 1)).cast(FloatDType::F16).into_data()
+        });
+
+        assert_eq!(out.dtype, DType::F16);
+        let exp = lhs_ref.matmul(rhs_ref).exp();
+        (exp.clone() / (exp + 1))
+            .into_data()
+            .assert_approx_eq::<FT>(&out, Tolerance::rel_abs(5e-2, 5e-2));
+    }
+
+    #[test]
+    #[serial]
+    fn should_fuse_matmul_with_broadcast_batch() {
+        let device = Default::default();
+        let ref_device = Default::default();
+        let lhs_ref =
+            ReferenceTensor::<3>::random([1, 4, 8], Distribution::Uniform(-1.0, 1.0), &ref_device);
+        let rhs_ref =
+            ReferenceTensor::<3>::random([3, 8, 5], Distribution::Uniform(-1.0, 1.0), &ref_device);
+        let lhs = TestTensor::<3>::from_data(lhs_ref.to_data(), &device);
+        let rhs = TestTensor::<3>::from_data(rhs_ref.to_data(), &device);
+
+        let out = assert_fused(&[1, 4, 8], &[3, 8, 5], || {
+            TestBackend::sync(&device);
+            (lhs.matmul(rhs) + 1).into_data()
+        });
+
+        assert_eq!(out.shape, [3, 4, 5]);
+        (lhs_ref.repeat_dim(0, 3).matmul(rhs_ref) + 1)
+            .into_data()
+            .assert_approx_eq::<FT>(&out, Tolerance::default());
+    }
+
+    #[test]
+    #[serial]
+    fn should_fuse_matmul_with_batch_swapped_lhs() {
+        let device = Default::default();
+        let ref_device = Default::default();
+        let lhs_ref = ReferenceTensor::<4>::random(
+            [2, 3, 4, 2],
+            Distribution::Uniform(-1.0, 1.0),
+            &ref_device,
+        );
+        let rhs_ref = ReferenceTensor::<4>::random(
+            [3, 2, 2, 5],
+            Distribution::Uniform(-1.0, 1.0),
+            &ref_device,
+        );
+
+        // Only the batch strides are swapped, each matrix stays contiguous.
+        let lhs = TestTensor::<4>::from_data(lhs_ref.to_data(), &device).swap_dims(0, 1);
+        let rhs = TestTensor::<4>::from_data(rhs_ref.to_data(), &device);
+        let lhs_ref = lhs_ref.swap_dims(0, 1);
+
+        let out = assert_fused(&[3, 2, 4, 2], &[3, 2, 2, 5], || {
+            TestBackend::sync(&device);
+            (lhs.matmul(rhs) + 1).into_data()
+        });
+
+        (lhs_ref.matmul(rhs_ref) + 1)
+            .into_data()
+            .assert_approx_eq::<FT>(&out, Tolerance::default());
+    }
+
+    #[test]
+    #[serial]
+    fn should_fuse_matmul_with_empty_batch() {
+        let device = Default::default();
+        let lhs = TestTensor::<3>::zeros([0, 4, 8], &device);
+        let rhs = TestTensor::zeros([0, 8, 5], &device);
+
+        let out = assert_fused(&[0, 4, 8], &[0, 8, 5], || {
+            TestBackend::sync(&device);
+            (lhs.matmul(rhs) + 1).into_data()
+        });
+
+        assert_eq!(out.shape, [0, 4, 5]);
+        assert_eq!(out.num_elements(), 0);
+    }
+
+    #[test]
+    #[serial]
+    fn should_fall_back_when_the_inner_dimension_is_empty() {
+        let device = Default::default();
+        let settings = settings();
+        let profiles = record_profiles(&settings);
+        settings.set_options(MatmulFusionOptions {
+            deterministic: true,
+            ..Default::default()
+        });
+
+        let lhs = TestTensor::<2>::zeros([3, 0], &device);
+        let rhs = TestTensor::zeros([0, 4], &device);
+
+        TestBackend::sync(&device);
+        let out = (lhs.matmul(rhs) + 1).into_data();
+
+        settings.set_options(Default::default());
+        settings.clear_profiling_hook();
+
+        // The output isn't empty, the sum over an empty dimension is zero.
+        out.assert_eq(&TensorData::from([[1.; 4]; 3]), false);
+        let profiles = profiles_of(&profiles, &[3, 0], &[0, 4]);
+        assert_eq!(profiles.len(), 1);
+        assert!(profiles[0].selector.is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn should_write_the_output_and_its_half_precision_copy() {
+        let device = Default::default();
+        let client = TestRuntime::client(&device);
+        if !supported_matmul_precisions::<TestRuntime>(&client).contains(&FusePrecision::F16) {
+            return;
+        }
+
+        let ref_device = Default::default();
+        let lhs_ref =
+            ReferenceTensor::<2>::random([4, 6], Distribution::Uniform(-1.0, 1.0), &ref_device);
+        let rhs_ref =
+            ReferenceTensor::<2>::random([6, 5], Distribution::Uniform(-1.0, 1.0), &ref_device);
+        let lhs = TestTensor::<2>::from_data(lhs_ref.to_data(), &device);
+        let rhs = TestTensor::<2>::from_data(rhs_ref.to_data(), &device);
+
+        let (out, out_f16) = assert_fused(&[4, 6], &[6, 5], || {
+            TestBackend::sync(&device);
+            let out = lhs.matmul(rhs) + 1;
+            let out_f16 = out.clone().cast(FloatDType::F16);
+            (out.into_data(), out_f16.into_data())
+        });
+
+        assert_eq!(out_f16.dtype, DType::F16);
+        let expected = (lhs_ref.matmul(rhs_ref) + 1).into_data();
+        expected.assert_approx_eq::<FT>(&out, Tolerance::default());
+        expected.assert_approx_eq::<FT>(&out_f16, Tolerance::rel_abs(1e-2, 1e-2));
+    }
+
+    #[test]
+    #[serial]
+    fn should_fuse_double_precision_with_the_unit_kernel() {
+        let device = Default::default();
+        let client = TestRuntime::client(&device);
+        if !supported_matmul_precisions::<TestRuntime>(&client).contains(&FusePrecision::F64) {
+            return;
+        }
+
+        let settings = settings();
+        let profiles = record_profiles(&settings);
+        settings.set_options(MatmulFusionOptions {
+            deterministic: true,
+            ..Default::default()
+        });
+
+        let ref_device = Default::default();
+        let lhs_ref =
+            ReferenceTensor::<2>::random([6, 7], Distribution::Uniform(-1.0, 1.0), &ref_device);
+        let rhs_ref =
+            ReferenceTensor::<2>::random([7, 3], Distribution::Uniform(-1.0, 1.0), &ref_device);
+        let lhs = TestTensor::<2>::from_data(lhs_ref.to_data(), &device).cast(FloatDType::F64);
+        let rhs = TestTensor::<2>::from_data(rhs_ref.to_data(), &device).cast(FloatDType::F64);
+
+        TestBackend::sync(&device);
+        let out = (lhs.matmul(rhs) + 1).into_data();
+
+        settings.set_options(Default::default());
+        settings.clear_profiling_hook();
+
+        assert_eq!(out.dtype, DType::F64);
+        (lhs_ref.matmul(rhs_ref) + 1)
+            .into_data()
+            .assert_approx_eq::<FT>(&out, Tolerance::default());
+        let profiles = profiles_of(&profiles, &[6, 7], &[7, 3]);
+        assert_eq!(profiles.len(), 1);
+        assert!(matches!(
+            profiles[0].selector,
+            Some(FusedMatmulSelector::SimpleUnit)
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn should_fall_back_on_mixed_operand_precisions() {
+        let device = Default::default();
+        let client = TestRuntime::client(&device);
+        let precisions = supported_matmul_precisions::<TestRuntime>(&client);
+        if !precisions.contains(&FusePrecision::F16) || !precisions.contains(&FusePrecision::BF16) {
+            return;
+        }
+
+        let settings = settings();
+        let profiles = record_profiles(&settings);
+        settings.set_options(MatmulFusionOptions {
+            deterministic: true,
+            ..Default::default()
+        });
+
+        let ref_device = Default::default();
+        let lhs_ref =
+            ReferenceTensor::<2>::random([5, 4], Distribution::Uniform(-1.0, 1.0), &ref_device);
+        let rhs_ref =
+            ReferenceTensor::<2>::random([4, 7], Distribution::Uniform(-1.0, 1.0), &ref_device);
+        let lhs = TestTensor::<2>::from_data(lhs_ref.to_data(), &device).cast(FloatDType::F16);
+        let rhs = TestTensor::<2>::from_data(rhs_ref.to_data(), &device).cast(FloatDType::BF16);
+
+        TestBackend::sync(&device);
+        let out = (lhs.matmul(rhs) + 1).into_data();
+
+        settings.set_options(Default::default());
+        settings.clear_profiling_hook();
+
+        (lhs_ref.matmul(rhs_ref) + 1)
+            .into_data()
+            .assert_approx_eq::<FT>(&out, Tolerance::rel_abs(5e-2, 5e-2));
+        let profiles = profiles_of(&profiles, &[5, 4], &[4, 7]);
+        assert_eq!(profiles.len(), 1);
+        assert!(profiles[0].selector.is_none());
+    }
+}
@@ -10,6 +10,7 @@ mod conv3d;
 mod conv_transpose2d;
 mod conv_transpose3d;
 mod cross;
+mod fusion_matmul;
 mod gather;
 mod mask_fill;
 mod mask_where;
@@ -143,7 +144,7 @@ macro_rules! testgen_jit_fusion {
     };
     ([$($float:ident),*], [$($int:ident),*], [$($bool:ident),*]) => {
         use super::*;
-        use burn_cubecl::tests::{burn_autodiff, burn_fusion, burn_ndarray, burn_tensor};
+        use burn_cubecl::tests::{burn_autodiff, burn_fusion, burn_ndarray, burn_tensor, serial_test};
 
         pub type TestBackend = burn_fusion::Fusion<CubeBackend<TestRuntime, f32, i32, u32>>;
         pub type TestBackend2<F, I, B> = burn_fusion::Fusion<CubeBackend<TestRuntime, F, I, B>>;
@@ -170,5 +171,7 @@ macro_rules! testgen_jit_fusion {
         burn_tensor::testgen_q_matmul!();
         burn_tensor::testgen_scheme!();
         burn_tensor::testgen_quantize!();
+
+        burn_cubecl::testgen_fusion_matmul!();
     };
 }
@@ -125,6 +125,60 @@ mod tests {
         tensor_3.into_data().assert_eq(&expected, false);
     }
 
+    #[test]
+    fn test_float_matmul_broadcast_batch_fused() {
+        let device = Default::default();
+        let lhs: TestTensor<3> = TestTensorInt::arange(0..32, &device)
+            .reshape([1, 4, 8])
+            .float();
+        let rhs: TestTensor<3> = TestTensorInt::arange(0..120, &device)
+            .reshape([3, 8, 5])
+            .float();
+
+        TestBackend::sync(&device);
+        // [1, 4, 8] @ [3, 8, 5] -> [3, 4, 5]
+        let out = lhs.clone().matmul(rhs.clone()) + 1;
+        let expected = lhs.repeat_dim(0, 3).matmul(rhs) + 1;
+
+        assert_eq!(out.dims(), [3, 4, 5]);
+        out.into_data()
+            .assert_approx_eq::<FT>(&expected.into_data(), Tolerance::default());
+    }
+
+    #[test]
+    fn test_float_matmul_batch_swapped_fused() {
+        let device = Default::default();
+        // [2, 3, 2, 2] -> [3, 2, 2, 2] with swapped batch strides.
+        let lhs: TestTensor<4> = TestTensorInt::arange(0..24, &device)
+            .reshape([2, 3, 2, 2])
+            .float()
+            .swap_dims(0, 1);
+        let rhs: TestTensor<4> = TestTensorInt::arange(0..24, &device)
+            .reshape([3, 2, 2, 2])
+            .float();
+        let lhs_contiguous = TestTensor::<4>::from_data(lhs.to_data(), &device);
+
+        TestBackend::sync(&device);
+        let out = lhs.matmul(rhs.clone()) + 1;
+        let expected = lhs_contiguous.matmul(rhs) + 1;
+
+        out.into_data()
+            .assert_approx_eq::<FT>(&expected.into_data(), Tolerance::default());
+    }
+
+    #[test]
+    fn test_float_matmul_empty_batch_fused() {
+        let device = Default::default();
+        let lhs = TestTensor::<3>::zeros([0, 4, 8], &device);
+        let rhs = TestTensor::<3>::zeros([0, 8, 5], &device);
+
+        TestBackend::sync(&device);
+        let out = lhs.matmul(rhs) + 1;
+
+        assert_eq!(out.dims(), [0, 4, 5]);
+        assert_eq!(out.into_data().num_elements(), 0);
+    }
+
     #[test]
     fn test_int_matmul_simple_1() {
         let device = Default::default();
@@ -309,6 +363,81 @@ mod tests {
         expected.assert_approx_eq(&out.into_data(), Tolerance::<f32>::strict());
     }
 
+    #[test]
+    fn test_float_matmul_activation() {
+        let device = Default::default();
+        let lhs = TestTensor::<2>::from_floats([[1., -2.], [3., -4.]], &device);
+        let rhs = TestTensor::from_floats([[1., 2.], [3., -1.]], &device);
+
+        TestBackend::sync(&device);
+        let out = burn_tensor::activation::relu(lhs.matmul(rhs));
+        let expected = TensorData::from([[0., 4.], [0., 10.]]);
+
+        out.into_data().assert_eq(&expected, false);
+    }
+
+    #[test]
+    fn test_float_matmul_f32_epilogue() {
+        let device = Default::default();
+        let lhs = TestTensor::<2>::from_floats([[1., 2.], [3., 4.]], &device);
+        let rhs = TestTensor::from_floats([[0.5, 0.], [0., -0.5]], &device);
+        let dtype = lhs.dtype();
+
+        TestBackend::sync(&device);
+        // The element-wise ops run in f32 between the casts, whatever the matmul precision.
+        let out = lhs.matmul(rhs).cast(burn_tensor::DType::F32);
+        let exp = out.exp();
+        let out = (exp.clone() / (exp + 1)).cast(dtype);
+        let expected = TensorData::from([[0.6224593, 0.2689414], [0.8175745, 0.1192029]]);
+
+        out.into_data()
+            .assert_approx_eq::<FT>(&expected, Tolerance::default());
+    }
+
+    #[test]
+    fn test_float_matmul_alpha_beta() {
+        let device = Default::default();
+        let a = TestTensor::<2>::from_floats([[1., 2.], [3., 4.]], &device);
+        let b = TestTensor::from_floats([[1., 1.], [0., 1.]], &device);
+        let c = TestTensor::from_floats([[2., 2.], [4., 4.]], &device);
+        let (alpha, beta) = (2.0, 0.5);
+
+        TestBackend::sync(&device);
+        // C = alpha * A @ B + beta * C, with C at its last use so it can be overwritten.
+        let c = a.matmul(b) * alpha + c * beta;
+        let expected = TensorData::from([[3., 7.], [8., 16.]]);
+
+        c.into_data()
+            .assert_approx_eq::<FT>(&expected, Tolerance::default());
+    }
+
+    #[test]
+    fn test_float_matmul_transposed_output() {
+        let device = Default::default();
+        let lhs = TestTensor::<2>::from_floats([[1., 2.], [3., 4.]], &device);
+        let rhs = TestTensor::from_floats([[5., 6.], [7., 8.]], &device);
+
+        TestBackend::sync(&device);
+        let out = lhs.matmul(rhs).transpose() + 1;
+        let expected = TensorData::from([[20., 44.], [23., 51.]]);
+
+        out.into_data().assert_eq(&expected, false);
+    }
+
+    #[test]
+    fn test_float_matmul_transposed_output_chained() {
+        let device = Default::default();
+        let lhs = TestTensor::<2>::from_floats([[1., 2.], [3., 4.]], &device);
+        let rhs = TestTensor::from_floats([[5., 6.], [7., 8.]], &device);
+        let next = TestTensor::from_floats([[1., 2.], [3., 4.]], &device);
+
+        TestBackend::sync(&device);
+        let out = (lhs.matmul(rhs) + 0).transpose().matmul(next);
+        let expected = TensorData::from([[148., 210.], [172., 244.]]);
+
+        out.into_data().assert_eq(&expected, false);
+    }
+
     #[test]
     fn test_int_matmul_4_8() {
         if (IntType::MAX as u32) < 6092 {